@@ -0,0 +1,153 @@
+use std::path::Path;
+#[cfg(test)]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+use ratatui::prelude::{Color, Style};
+use ratatui::text::{Line, Span};
+use tree_sitter_highlight::{HighlightConfiguration, HighlightEvent, Highlighter};
+
+// capture names we ask tree-sitter to recognize, and the style each is rendered with
+const HIGHLIGHT_STYLES: [(&str, Color); 12] = [
+    ("attribute", Color::Yellow),
+    ("comment", Color::DarkGray),
+    ("constant", Color::Cyan),
+    ("constructor", Color::Yellow),
+    ("function", Color::Blue),
+    ("keyword", Color::Magenta),
+    ("label", Color::Cyan),
+    ("operator", Color::White),
+    ("property", Color::Cyan),
+    ("string", Color::Green),
+    ("type", Color::Yellow),
+    ("variable.builtin", Color::Red),
+];
+
+// built on first use; `None` if the grammar's queries failed to load
+static RUST_CONFIG: OnceLock<Option<HighlightConfiguration>> = OnceLock::new();
+
+#[cfg(test)]
+static CONFIG_BUILDS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Lang {
+    Rust,
+}
+
+impl Lang {
+    pub fn from_path(path: &Path) -> Option<Lang> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("rs") => Some(Lang::Rust),
+            _ => None,
+        }
+    }
+
+    fn config(&self) -> Option<&'static HighlightConfiguration> {
+        match self {
+            Lang::Rust => RUST_CONFIG.get_or_init(|| self.build_config()).as_ref(),
+        }
+    }
+
+    fn build_config(&self) -> Option<HighlightConfiguration> {
+        #[cfg(test)]
+        CONFIG_BUILDS.fetch_add(1, Ordering::SeqCst);
+
+        let mut config = match self {
+            Lang::Rust => HighlightConfiguration::new(
+                tree_sitter_rust::language(),
+                "rust",
+                tree_sitter_rust::HIGHLIGHTS_QUERY,
+                tree_sitter_rust::INJECTIONS_QUERY,
+                "",
+            ).ok()?,
+        };
+        let names: Vec<&str> = HIGHLIGHT_STYLES.iter().map(|(name, _)| *name).collect();
+        config.configure(&names);
+        Some(config)
+    }
+}
+
+/// Plain, unstyled lines; used for unknown file types or if highlighting fails.
+pub fn plain_lines(source: &str) -> Vec<Line<'static>> {
+    source.lines()
+        .map(|line| Line::from(line.to_string()))
+        .collect()
+}
+
+/// Split `source` into lines of styled spans according to the tree-sitter grammar for `lang`.
+pub fn highlight_to_lines(source: &str, lang: Lang) -> Vec<Line<'static>> {
+    highlight(source, lang).unwrap_or_else(|| plain_lines(source))
+}
+
+fn highlight(source: &str, lang: Lang) -> Option<Vec<Line<'static>>> {
+    let config = lang.config()?;
+    let mut highlighter = Highlighter::new();
+    let events = highlighter
+        .highlight(config, source.as_bytes(), None, |_| None)
+        .ok()?;
+
+    let mut lines = Vec::new();
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut styles: Vec<Style> = Vec::new(); // nested highlights, innermost last
+
+    for event in events {
+        match event.ok()? {
+            HighlightEvent::HighlightStart(h) => {
+                styles.push(Style::default().fg(HIGHLIGHT_STYLES[h.0].1));
+            }
+            HighlightEvent::HighlightEnd => {
+                styles.pop();
+            }
+            HighlightEvent::Source { start, end } => {
+                let style = styles.last().copied().unwrap_or_default();
+                let mut parts = source[start..end].split('\n').peekable();
+                while let Some(part) = parts.next() {
+                    let part = part.strip_suffix('\r').unwrap_or(part);
+                    if !part.is_empty() {
+                        spans.push(Span::styled(part.to_string(), style));
+                    }
+                    // every part but the last was terminated by a newline
+                    if parts.peek().is_some() {
+                        lines.push(Line::from(std::mem::take(&mut spans)));
+                    }
+                }
+            }
+        }
+    }
+
+    if !spans.is_empty() {
+        lines.push(Line::from(spans));
+    }
+
+    Some(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rust_keyword_is_styled() {
+        let lines = highlight_to_lines("fn main() {\n    let x = 1;\n}\n", Lang::Rust);
+        assert_eq!(lines.len(), 3);
+
+        let fn_span = lines[0].spans.iter().find(|span| span.content == "fn").unwrap();
+        assert_eq!(fn_span.style.fg, Some(Color::Magenta));
+
+        let let_span = lines[1].spans.iter().find(|span| span.content == "let").unwrap();
+        assert_eq!(let_span.style.fg, Some(Color::Magenta));
+    }
+
+    #[test]
+    fn config_is_built_once() {
+        highlight_to_lines("let a = 1;", Lang::Rust);
+        highlight_to_lines("let b = 2;", Lang::Rust);
+        assert_eq!(CONFIG_BUILDS.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn unknown_extension_is_not_highlighted() {
+        assert_eq!(Lang::from_path(Path::new("notes.txt")), None);
+        assert_eq!(Lang::from_path(Path::new("src/main.rs")), Some(Lang::Rust));
+    }
+}
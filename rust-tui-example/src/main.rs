@@ -2,28 +2,48 @@ use std::cmp::PartialEq;
 use std::env::args;
 use std::error::Error;
 use std::fs::{File, Metadata};
-use std::io::{BufRead, BufReader};
+use std::io::{IsTerminal, Read};
 use std::path::Path;
 
 use chrono::{DateTime, Local};
 use crossterm::event::KeyCode;
 use ratatui::Frame;
-use ratatui::layout::{Constraint, Direction, Flex, Layout, Margin};
+use ratatui::layout::{Constraint, Direction, Flex, Layout, Margin, Rect};
 use ratatui::prelude::{Color, Modifier, Style, Text};
 use ratatui::text::Line;
-use ratatui::widgets::{Block, Borders, Padding, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap};
+use ratatui::widgets::{Block, Borders, Clear, Padding, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap};
 
+mod highlight;
 mod tui;
 
-#[derive(PartialEq, Clone, Copy)]
+#[derive(PartialEq, Debug, Clone, Copy)]
 enum Action {
     ScrollUp,
     ScrollDown,
+    ScrollLeft,
+    ScrollRight,
+    ToggleWrap,
+    Resize,
     Home,
     End,
+    Goto(usize),
+    Prompt,
+    PromptInput(char),
+    PromptBackspace,
+    PromptSubmit,
+    PromptCancel,
+    ToggleHelp,
     Quit,
 }
 
+// which set of key bindings applies
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum Mode {
+    Normal,
+    Prompt,
+    Help,
+}
+
 struct ScrollState {
     state: ScrollbarState,
     position: usize,
@@ -32,9 +52,44 @@ struct ScrollState {
 struct FileData {
     path: String,
     data: Vec<Line<'static>>,
-    metadata: Metadata,
+    metadata: Option<Metadata>, // None when reading from stdin
     action: Option<Action>,
     scroll_state: ScrollState,
+    prompt: Option<String>, // input buffer for the ':' goto-line prompt, if active
+    wrap_enabled: bool,
+    h_offset: usize, // horizontal scroll, only used when wrapping is off
+    show_help: bool,
+}
+
+impl FileData {
+    fn new(path: String, data: Vec<Line<'static>>, metadata: Option<Metadata>) -> FileData {
+        let scroll_state = ScrollState {
+            state: ScrollbarState::new(data.len()),
+            position: 0,
+        };
+
+        FileData {
+            path,
+            data,
+            metadata,
+            action: None,
+            scroll_state,
+            prompt: None,
+            wrap_enabled: true,
+            h_offset: 0,
+            show_help: false,
+        }
+    }
+
+    fn mode(&self) -> Mode {
+        if self.show_help {
+            Mode::Help
+        } else if self.prompt.is_some() {
+            Mode::Prompt
+        } else {
+            Mode::Normal
+        }
+    }
 }
 
 #[tokio::main(flavor = "current_thread")]
@@ -49,13 +104,18 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     tui.enter()?; // Starts event handler, enters raw mode, enters alternate screen
 
+    let mut redraw = true; // always draw the first frame
+
     loop {
-        tui.draw(|f| { // Deref allows calling `tui.terminal.draw`
-            ui(f, &mut file_data);
-        })?;
+        if redraw {
+            tui.draw(|f| { // Deref allows calling `tui.terminal.draw`
+                ui(f, &mut file_data);
+            })?;
+        }
 
         if let Some(evt) = tui.next().await { // `tui.next().await` blocks till next event
-            let some_action = map_event(evt);
+            let some_action = map_event(&evt, file_data.mode());
+            redraw = should_redraw(&evt, some_action);
             file_data.action = some_action;
 
             if is_quit_action(&mut file_data) {
@@ -69,6 +129,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+// idle ticks/renders leave the screen as is; only state changes and resizes need a new frame
+fn should_redraw(event: &tui::Event, action: Option<Action>) -> bool {
+    action.is_some() || matches!(event, tui::Event::Resize(_, _))
+}
+
 fn is_quit_action(file_data: &mut FileData) -> bool {
     file_data.action.is_some_and(|action| action == Action::Quit)
 }
@@ -79,37 +144,47 @@ fn get_file_data(args: Vec<String>) -> Result<FileData, Box<dyn Error>> {
         let file_path = Path::new(path.as_str());
 
         if file_path.exists() && file_path.is_file() {
-            let file = File::open(file_path).unwrap();
-            let reader = BufReader::new(file);
-            let data : Vec<Line> = reader.lines()
-                .map(|line| { Line::from(line.unwrap()) })
-                .collect::<Vec<_>>();
-
-            let scroll_state = ScrollState {
-                state: ScrollbarState::new(data.len()),
-                position: 0,
+            let mut source = String::new();
+            File::open(file_path)?.read_to_string(&mut source)?;
+            let data = match highlight::Lang::from_path(file_path) {
+                Some(lang) => highlight::highlight_to_lines(&source, lang),
+                None => highlight::plain_lines(&source),
             };
 
             let metadata = file_path.metadata().unwrap();
-            Ok(FileData {
-                path: file_path.to_str().unwrap().to_string(),
-                data,
-                metadata,
-                action: None,
-                scroll_state,
-            })
+            Ok(FileData::new(file_path.to_str().unwrap().to_string(), data, Some(metadata)))
         } else {
             // todo: return Error
             panic!("file does not exist or cannot be read")
         }
+    } else if args.len() == 1 && !std::io::stdin().is_terminal() {
+        // no file given but something is piped in e.g. `ls -l | rust-tui-example`
+        let data = read_lines(std::io::stdin().lock())?;
+        Ok(FileData::new("(stdin)".to_string(), data, None))
     } else {
         // todo: return Error
         panic!("missing file name argument")
     }
 }
 
-fn map_event(event: tui::Event) -> Option<Action> {
+fn read_lines(mut reader: impl Read) -> Result<Vec<Line<'static>>, Box<dyn Error>> {
+    let mut source = String::new();
+    reader.read_to_string(&mut source)?;
+    Ok(highlight::plain_lines(&source))
+}
+
+fn map_event(event: &tui::Event, mode: Mode) -> Option<Action> {
+    if let tui::Event::Resize(_, _) = event {
+        return Some(Action::Resize);
+    }
+
     if let tui::Event::Key(key) = event {
+        match mode {
+            Mode::Prompt => return map_prompt_key(key.code),
+            Mode::Help => return Some(Action::ToggleHelp), // any key dismisses the help
+            Mode::Normal => {}
+        }
+
         return match key.code {
             KeyCode::Up => {
                 Some(Action::ScrollUp)
@@ -117,12 +192,27 @@ fn map_event(event: tui::Event) -> Option<Action> {
             KeyCode::Down => {
                 Some(Action::ScrollDown)
             }
+            KeyCode::Left => {
+                Some(Action::ScrollLeft)
+            }
+            KeyCode::Right => {
+                Some(Action::ScrollRight)
+            }
             KeyCode::Home => {
                 Some(Action::Home)
             }
             KeyCode::End => {
                 Some(Action::End)
             }
+            KeyCode::Char('w') | KeyCode::Char('W') => {
+                Some(Action::ToggleWrap)
+            }
+            KeyCode::Char(':') => {
+                Some(Action::Prompt)
+            }
+            KeyCode::Char('?') | KeyCode::F(1) => {
+                Some(Action::ToggleHelp)
+            }
             KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
                 Some(Action::Quit)
             }
@@ -134,6 +224,26 @@ fn map_event(event: tui::Event) -> Option<Action> {
     None
 }
 
+fn map_prompt_key(code: KeyCode) -> Option<Action> {
+    match code {
+        KeyCode::Char(c) => {
+            Some(Action::PromptInput(c))
+        }
+        KeyCode::Backspace => {
+            Some(Action::PromptBackspace)
+        }
+        KeyCode::Enter => {
+            Some(Action::PromptSubmit)
+        }
+        KeyCode::Esc => {
+            Some(Action::PromptCancel)
+        }
+        _ => {
+            None
+        }
+    }
+}
+
 fn ui(frame: &mut Frame, file_data: &mut FileData) {
     let area = frame.size();
 
@@ -153,13 +263,19 @@ fn ui(frame: &mut Frame, file_data: &mut FileData) {
         .title(file_data.path.clone())
         .title_style(style_blue_bold);
 
+    // the main block has a border and a padding of 1 on top and bottom
+    update_viewport(file_data, chunks[0].height.saturating_sub(4) as usize);
+    update_help_state(file_data);
+    update_prompt_state(file_data);
     update_scroll_state(file_data);
 
     let text = file_data.data.to_vec();
-    let main_content = Paragraph::new(text)
-        .scroll((file_data.scroll_state.position  as u16, 0))
-        .block(main_content_block)
-        .wrap(Wrap { trim: false }); // 'trim: false' preserves indenting i.e. no strip whitespace
+    let mut main_content = Paragraph::new(text)
+        .scroll((file_data.scroll_state.position as u16, file_data.h_offset as u16))
+        .block(main_content_block);
+    if file_data.wrap_enabled {
+        main_content = main_content.wrap(Wrap { trim: false }); // 'trim: false' preserves indenting i.e. no strip whitespace
+    }
     frame.render_widget(main_content, chunks[0]);
 
     let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight);
@@ -178,20 +294,84 @@ fn ui(frame: &mut Frame, file_data: &mut FileData) {
         .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
         .split(chunks[1]);
 
-    let footer_commands = Text::from("↑ ↓ <Home> <End>");
+    let footer_commands = match &file_data.prompt {
+        Some(input) => Text::from(format!(":{}", input)),
+        None => Text::from("↑ ↓ ← → <Home> <End> :<line> w(rap) ?(help)"),
+    };
     let footer_commands_paragraph = Paragraph::new(footer_commands)
         .style(style_blue_bold)
         .left_aligned();
     frame.render_widget(footer_commands_paragraph, footer_layout[0]);
 
-    let system_time = file_data.metadata.created().unwrap();
-    let local_time: DateTime<Local> = system_time.into();
-    let file_details = format!("Created: {} Length: {}", local_time.format("%d-%m-%Y %H:%M"), file_data.metadata.len());
+    let percent = scroll_percent(file_data.scroll_state.position, file_data.data.len());
+    let file_details = match &file_data.metadata {
+        Some(metadata) => {
+            let system_time = metadata.created().unwrap();
+            let local_time: DateTime<Local> = system_time.into();
+            format!("Created: {} Length: {} {:>3}%",
+                    local_time.format("%d-%m-%Y %H:%M"), metadata.len(), percent)
+        }
+        None => format!("{:>3}%", percent),
+    };
     let footer_metadata = Text::from(file_details);
     let footer_metadata_paragraph = Paragraph::new(footer_metadata)
         .style(style_blue_bold)
         .right_aligned();
     frame.render_widget(footer_metadata_paragraph, footer_layout[1]);
+
+    if file_data.show_help {
+        render_help(frame, area, style_blue_bold);
+    }
+}
+
+const HELP_TEXT: [&str; 8] = [
+    "↑ / ↓        scroll up / down",
+    "← / →        scroll left / right (wrap off)",
+    "<Home>       go to start of file",
+    "<End>        go to end of file",
+    ":<line>      go to line",
+    "w            toggle line wrapping",
+    "? / <F1>     toggle this help",
+    "q / <Esc>    quit",
+];
+
+fn render_help(frame: &mut Frame, area: Rect, title_style: Style) {
+    let lines: Vec<Line> = HELP_TEXT.iter()
+        .map(|line| Line::from(*line))
+        .chain([Line::from(""), Line::from("press any key to close")])
+        .collect();
+
+    // content plus border and padding
+    let width = lines.iter().map(|line| line.width()).max().unwrap_or(0) as u16 + 4;
+    let height = lines.len() as u16 + 4;
+    let popup_area = centered_rect(area, width, height);
+
+    let help_block = Block::new()
+        .borders(Borders::all())
+        .padding(Padding::new(1, 1, 1, 1))
+        .title("Help")
+        .title_style(title_style);
+    let help = Paragraph::new(lines).block(help_block);
+
+    frame.render_widget(Clear, popup_area); // blank out the file content under the popup
+    frame.render_widget(help, popup_area);
+}
+
+fn centered_rect(area: Rect, width: u16, height: u16) -> Rect {
+    let [row] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [rect] = Layout::horizontal([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(row);
+    rect
+}
+
+fn update_help_state(file_data: &mut FileData) {
+    if file_data.action == Some(Action::ToggleHelp) {
+        file_data.show_help = !file_data.show_help;
+        file_data.action = None;
+    }
 }
 
 fn update_scroll_state(file_data: &mut FileData) {
@@ -207,6 +387,13 @@ fn update_scroll_state(file_data: &mut FileData) {
                 file_data.scroll_state.position =
                     file_data.scroll_state.position.saturating_add(1);
             }
+            Action::ScrollLeft | Action::ScrollRight if !file_data.wrap_enabled => {
+                file_data.h_offset = scroll_horizontal(file_data.h_offset, action);
+            }
+            Action::ToggleWrap => {
+                file_data.wrap_enabled = !file_data.wrap_enabled;
+                file_data.h_offset = 0;
+            }
             Action::Home => {
                 file_data.scroll_state.state.first();
                 file_data.scroll_state.position = 0;
@@ -215,6 +402,11 @@ fn update_scroll_state(file_data: &mut FileData) {
                 file_data.scroll_state.position = file_data.data.len();
                 let _ = file_data.scroll_state.state.position(file_data.scroll_state.position);
             }
+            Action::Goto(line) => {
+                file_data.scroll_state.position = goto_line(line, file_data.data.len());
+                file_data.scroll_state.state =
+                    file_data.scroll_state.state.position(file_data.scroll_state.position);
+            }
             _ => {}
         }
 
@@ -222,3 +414,167 @@ fn update_scroll_state(file_data: &mut FileData) {
         file_data.action = None;
     }
 }
+
+fn update_viewport(file_data: &mut FileData, viewport_height: usize) {
+    if file_data.action == Some(Action::Resize) {
+        file_data.scroll_state.position =
+            reclamp_position(file_data.scroll_state.position, file_data.data.len(), viewport_height);
+        file_data.scroll_state.state =
+            file_data.scroll_state.state.position(file_data.scroll_state.position);
+        file_data.action = None;
+    }
+}
+
+/// Keep the scroll position from going past what a viewport of `height` rows can show,
+/// keeping the top line where it is whenever possible.
+fn reclamp_position(position: usize, len: usize, height: usize) -> usize {
+    position.min(len.saturating_sub(height))
+}
+
+fn update_prompt_state(file_data: &mut FileData) {
+    if let Some(action) = file_data.action {
+        match action {
+            Action::Prompt => {
+                file_data.prompt = Some(String::new());
+            }
+            Action::PromptInput(c) => {
+                if let Some(input) = file_data.prompt.as_mut() {
+                    input.push(c);
+                }
+            }
+            Action::PromptBackspace => {
+                if let Some(input) = file_data.prompt.as_mut() {
+                    input.pop();
+                }
+            }
+            Action::PromptCancel => {
+                file_data.prompt = None;
+            }
+            Action::PromptSubmit => {
+                // non-numeric input is just ignored, closing the prompt without moving
+                let target = file_data.prompt.take()
+                    .and_then(|input| input.trim().parse::<usize>().ok());
+                file_data.action = target.map(Action::Goto);
+                return;
+            }
+            _ => {
+                return;
+            }
+        }
+
+        file_data.action = None;
+    }
+}
+
+fn scroll_horizontal(h_offset: usize, action: Action) -> usize {
+    match action {
+        Action::ScrollLeft => h_offset.saturating_sub(1),
+        Action::ScrollRight => h_offset.saturating_add(1),
+        _ => h_offset,
+    }
+}
+
+/// How far through the file the scroll position is, 0-100; an empty file is always 100%.
+fn scroll_percent(position: usize, len: usize) -> usize {
+    if len == 0 {
+        return 100;
+    }
+    (position.min(len) * 100) / len
+}
+
+/// Map a 1-based line number to a scroll position, clamped to the buffer length.
+fn goto_line(line: usize, len: usize) -> usize {
+    line.saturating_sub(1).min(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyEvent;
+
+    use super::*;
+
+    fn key(code: KeyCode) -> tui::Event {
+        tui::Event::Key(KeyEvent::from(code))
+    }
+
+    #[test]
+    fn redraw_only_on_action_or_resize() {
+        assert!(!should_redraw(&tui::Event::Tick, None));
+        assert!(!should_redraw(&tui::Event::Render, None));
+        assert!(should_redraw(&tui::Event::Resize(80, 24), None));
+        assert!(should_redraw(&tui::Event::Resize(80, 24), Some(Action::Resize)));
+
+        assert!(should_redraw(&key(KeyCode::Down), Some(Action::ScrollDown)));
+        assert!(!should_redraw(&key(KeyCode::F(12)), None));
+    }
+
+    #[test]
+    fn reclamp_keeps_position_in_range() {
+        // somewhere in the middle, unaffected by shrinking or growing
+        assert_eq!(reclamp_position(10, 100, 10), 10);
+        assert_eq!(reclamp_position(10, 100, 40), 10);
+        // at the top of a file that no longer fits, the top line stays in view
+        assert_eq!(reclamp_position(0, 15, 10), 0);
+        // near the bottom, shrinking keeps the position, growing scrolls back to fill the view
+        assert_eq!(reclamp_position(80, 100, 10), 80);
+        assert_eq!(reclamp_position(80, 100, 40), 60);
+        // past the end, e.g. after <End>
+        assert_eq!(reclamp_position(100, 100, 10), 90);
+        // whole file fits
+        assert_eq!(reclamp_position(3, 5, 10), 0);
+    }
+
+    #[test]
+    fn help_toggles_open_and_any_key_closes_it() {
+        let mut file_data = FileData::new(String::new(), Vec::new(), None);
+        assert_eq!(file_data.mode(), Mode::Normal);
+
+        file_data.action = map_event(&key(KeyCode::Char('?')), file_data.mode());
+        update_help_state(&mut file_data);
+        assert_eq!(file_data.mode(), Mode::Help);
+        assert_eq!(file_data.action, None);
+
+        // keys that would normally scroll or quit just close the help
+        assert_eq!(map_event(&key(KeyCode::Char('q')), file_data.mode()), Some(Action::ToggleHelp));
+        file_data.action = map_event(&key(KeyCode::Down), file_data.mode());
+        update_help_state(&mut file_data);
+        assert_eq!(file_data.mode(), Mode::Normal);
+
+        file_data.action = map_event(&key(KeyCode::F(1)), file_data.mode());
+        update_help_state(&mut file_data);
+        assert_eq!(file_data.mode(), Mode::Help);
+    }
+
+    #[test]
+    fn read_lines_from_piped_input() {
+        let input = std::io::Cursor::new("first\n\n  third\r\nlast");
+        let data = read_lines(input).unwrap();
+        let lines: Vec<String> = data.iter().map(|line| line.to_string()).collect();
+        assert_eq!(lines, vec!["first", "", "  third", "last"]);
+    }
+
+    #[test]
+    fn goto_line_is_clamped_to_buffer() {
+        assert_eq!(goto_line(1, 100), 0);
+        assert_eq!(goto_line(42, 100), 41);
+        assert_eq!(goto_line(0, 100), 0);
+        assert_eq!(goto_line(500, 100), 100);
+        assert_eq!(goto_line(10, 0), 0);
+    }
+
+    #[test]
+    fn horizontal_scroll_moves_and_stops_at_zero() {
+        assert_eq!(scroll_horizontal(0, Action::ScrollRight), 1);
+        assert_eq!(scroll_horizontal(5, Action::ScrollLeft), 4);
+        assert_eq!(scroll_horizontal(0, Action::ScrollLeft), 0);
+        assert_eq!(scroll_horizontal(3, Action::Home), 3);
+    }
+
+    #[test]
+    fn scroll_percent_handles_empty_file() {
+        assert_eq!(scroll_percent(0, 200), 0);
+        assert_eq!(scroll_percent(50, 200), 25);
+        assert_eq!(scroll_percent(200, 200), 100);
+        assert_eq!(scroll_percent(0, 0), 100);
+    }
+}